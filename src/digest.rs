@@ -0,0 +1,170 @@
+use md5::{Digest, Md5};
+use sha1::Sha1;
+
+use crate::{DiscIO, ImageError};
+
+const READ_CHUNK_SIZE: usize = 0x10000;
+
+/// CRC32, MD5, and SHA-1 of a disc image's full logical contents, computed
+/// together in a single streaming pass over its `DiscIO` backend.
+pub struct Digests {
+    pub crc32: u32,
+    pub md5: [u8; 16],
+    pub sha1: [u8; 20]
+}
+
+/// A known-good dump record, e.g. one row of a Redump datfile, used to
+/// check an image against a verified no-intro/Redump database.
+pub struct KnownDump {
+    pub game_code: String,
+    pub size: u64,
+    pub crc32: u32,
+    pub sha1: [u8; 20]
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyResult {
+    /// The image's digests and size matched a known-good dump.
+    Verified,
+    /// No record in the supplied table matched this image.
+    Mismatch
+}
+
+pub(crate) fn compute_digests(io: &mut dyn DiscIO) -> Result<Digests, ImageError> {
+    let mut crc32 = crc32fast::Hasher::new();
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+
+    let len = io.len();
+    let mut buf = [0u8; READ_CHUNK_SIZE];
+    let mut offset = 0u64;
+    while offset < len {
+        let chunk_len = buf.len().min((len - offset) as usize);
+        io.read_at(offset, &mut buf[..chunk_len])?;
+
+        crc32.update(&buf[..chunk_len]);
+        md5.update(&buf[..chunk_len]);
+        sha1.update(&buf[..chunk_len]);
+
+        offset += chunk_len as u64;
+    }
+
+    Ok(Digests {
+        crc32: crc32.finalize(),
+        md5: md5.finalize().into(),
+        sha1: sha1.finalize().into()
+    })
+}
+
+pub(crate) fn verify(game_code: &str, size: u64, digests: &Digests, known_dumps: &[KnownDump]) -> VerifyResult {
+    let matched = known_dumps.iter().any(|dump| {
+        dump.game_code == game_code
+            && dump.size == size
+            && dump.crc32 == digests.crc32
+            && dump.sha1 == digests.sha1
+    });
+
+    if matched {
+        VerifyResult::Verified
+    } else {
+        VerifyResult::Mismatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    /// A `DiscIO` backed by an in-memory buffer, for hashing known content
+    /// without a real disc image on disk.
+    struct MemDiscIO {
+        data: Vec<u8>
+    }
+
+    impl DiscIO for MemDiscIO {
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+            let start = offset as usize;
+            let end = start + buf.len();
+            if end > self.data.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "read past end of test image"));
+            }
+            buf.copy_from_slice(&self.data[start..end]);
+            Ok(())
+        }
+
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+    }
+
+    //Digests of b"GC-IMAGE-DIGEST-TEST-FIXTURE-0123456789", cross-checked against Python's
+    //zlib.crc32/hashlib.md5/hashlib.sha1.
+    const FIXTURE: &[u8] = b"GC-IMAGE-DIGEST-TEST-FIXTURE-0123456789";
+    const FIXTURE_CRC32: u32 = 0xd0b0e70a;
+    const FIXTURE_MD5: [u8; 16] = [
+        0x50, 0xa8, 0xe9, 0x6c, 0xa1, 0xbd, 0xb7, 0xfc, 0xc4, 0xf4, 0x01, 0x28, 0x88, 0x24, 0xc6, 0xb9
+    ];
+    const FIXTURE_SHA1: [u8; 20] = [
+        0x72, 0xdc, 0x22, 0x9d, 0x5d, 0x70, 0x71, 0x70, 0x98, 0xc8, 0x70, 0xd5, 0x9f, 0x92, 0xbb, 0x4b,
+        0x47, 0xba, 0xbf, 0xb4
+    ];
+
+    #[test]
+    fn compute_digests_matches_known_hashes() {
+        let mut io = MemDiscIO { data: FIXTURE.to_vec() };
+        let digests = compute_digests(&mut io).unwrap();
+
+        assert_eq!(digests.crc32, FIXTURE_CRC32);
+        assert_eq!(digests.md5, FIXTURE_MD5);
+        assert_eq!(digests.sha1, FIXTURE_SHA1);
+    }
+
+    #[test]
+    fn verify_matches_a_known_dump() {
+        let digests = Digests {
+            crc32: FIXTURE_CRC32,
+            md5: FIXTURE_MD5,
+            sha1: FIXTURE_SHA1
+        };
+        let known_dumps = vec![KnownDump {
+            game_code: "GALE01".to_string(),
+            size: FIXTURE.len() as u64,
+            crc32: FIXTURE_CRC32,
+            sha1: FIXTURE_SHA1
+        }];
+
+        let result = verify("GALE01", FIXTURE.len() as u64, &digests, &known_dumps);
+        assert_eq!(result, VerifyResult::Verified);
+    }
+
+    #[test]
+    fn verify_rejects_a_mismatched_game_code_size_or_hash() {
+        let digests = Digests {
+            crc32: FIXTURE_CRC32,
+            md5: FIXTURE_MD5,
+            sha1: FIXTURE_SHA1
+        };
+        let known_dumps = vec![KnownDump {
+            game_code: "GALE01".to_string(),
+            size: FIXTURE.len() as u64,
+            crc32: FIXTURE_CRC32,
+            sha1: FIXTURE_SHA1
+        }];
+
+        //Wrong game code.
+        assert_eq!(verify("GALP01", FIXTURE.len() as u64, &digests, &known_dumps), VerifyResult::Mismatch);
+        //Wrong size.
+        assert_eq!(verify("GALE01", FIXTURE.len() as u64 + 1, &digests, &known_dumps), VerifyResult::Mismatch);
+        //Wrong hash.
+        let bad_hash_dumps = vec![KnownDump {
+            game_code: "GALE01".to_string(),
+            size: FIXTURE.len() as u64,
+            crc32: FIXTURE_CRC32 ^ 1,
+            sha1: FIXTURE_SHA1
+        }];
+        assert_eq!(verify("GALE01", FIXTURE.len() as u64, &digests, &bad_hash_dumps), VerifyResult::Mismatch);
+        //Empty table.
+        assert_eq!(verify("GALE01", FIXTURE.len() as u64, &digests, &[]), VerifyResult::Mismatch);
+    }
+}
@@ -0,0 +1,414 @@
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::ImageError;
+
+const CISO_MAGIC: [u8; 4] = *b"CISO";
+const CISO_HEADER_SIZE: usize = 0x8000;
+const CISO_MAP_SIZE: usize = 32760;
+
+/// A source of disc bytes, addressable by logical offset.
+///
+/// `GCImage` reads everything through this trait so the header/FST/banner
+/// parsing doesn't care whether the bytes come from a raw `.iso`, a
+/// compressed `.ciso`, a split image, or a decrypted Wii partition.
+pub trait DiscIO {
+    /// Reads `buf.len()` bytes starting at logical offset `offset`, filling `buf` entirely.
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+
+    /// The logical length of the disc image, in bytes.
+    fn len(&self) -> u64;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A `DiscIO` backend that reads directly from an uncompressed disc image file.
+pub struct RawFileIO {
+    file: fs::File,
+    len: u64
+}
+
+impl RawFileIO {
+    pub fn open(path: &Path) -> Result<RawFileIO, ImageError> {
+        let len = fs::metadata(path)?.len();
+        let file = fs::File::open(path)?;
+        Ok(RawFileIO {
+            file,
+            len
+        })
+    }
+}
+
+impl DiscIO for RawFileIO {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.file.seek(io::SeekFrom::Start(offset))?;
+        self.file.read_exact(buf)
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// A `DiscIO` backend for CISO-compressed disc images.
+///
+/// CISO files start with a 0x8000-byte header: the magic `"CISO"`, a
+/// little-endian block size, and a 32760-byte presence map (one byte per
+/// block, non-zero meaning the block is stored). Stored blocks follow the
+/// header back to back, in order; blocks whose map entry is zero are all
+/// zero bytes and aren't stored at all.
+pub struct CisoIO {
+    file: fs::File,
+    block_size: u32,
+    len: u64,
+    /// For each logical block, the number of *present* blocks before it
+    /// (used to compute its offset in the stored block stream), or `None`
+    /// if the block itself isn't stored.
+    block_offsets: Vec<Option<u32>>
+}
+
+impl CisoIO {
+    pub fn open(path: &Path) -> Result<CisoIO, ImageError> {
+        let mut file = fs::File::open(path)?;
+        let mut header = [0u8; CISO_HEADER_SIZE];
+        file.read_exact(&mut header)?;
+
+        if header[0..4] != CISO_MAGIC {
+            return Err(ImageError::InvalidFormat("missing CISO magic".to_string()));
+        }
+        let block_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if block_size == 0 {
+            return Err(ImageError::InvalidFormat("zero block size".to_string()));
+        }
+        let map = &header[8..8 + CISO_MAP_SIZE];
+
+        let mut block_offsets = Vec::with_capacity(CISO_MAP_SIZE);
+        let mut present_count = 0u32;
+        for &present in map {
+            if present != 0 {
+                block_offsets.push(Some(present_count));
+                present_count += 1;
+            } else {
+                block_offsets.push(None);
+            }
+        }
+        //The map always spans the full CISO_MAP_SIZE capacity, but the image's real logical
+        //size is only as large as its last present block - trailing absent blocks aren't
+        //part of the original dump, just unused map capacity.
+        let len = match map.iter().rposition(|&present| present != 0) {
+            Some(last_present_block) => (last_present_block as u64 + 1) * block_size as u64,
+            None => 0
+        };
+
+        Ok(CisoIO {
+            file,
+            block_size,
+            len,
+            block_offsets
+        })
+    }
+
+    fn read_block(&mut self, block: usize, block_offset: usize, out: &mut [u8]) -> io::Result<()> {
+        match self.block_offsets.get(block).copied().flatten() {
+            None => {
+                out.iter_mut().for_each(|b| *b = 0);
+                Ok(())
+            },
+            Some(present_count) => {
+                let file_ofst = CISO_HEADER_SIZE as u64
+                    + (present_count as u64 * self.block_size as u64)
+                    + block_offset as u64;
+                self.file.seek(io::SeekFrom::Start(file_ofst))?;
+                self.file.read_exact(out)
+            }
+        }
+    }
+}
+
+impl DiscIO for CisoIO {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let block_size = self.block_size as u64;
+        let mut remaining = buf;
+        let mut offset = offset;
+
+        while !remaining.is_empty() {
+            let block = (offset / block_size) as usize;
+            let block_offset = (offset % block_size) as usize;
+            let chunk_len = remaining.len().min(block_size as usize - block_offset);
+
+            let (chunk, rest) = remaining.split_at_mut(chunk_len);
+            self.read_block(block, block_offset, chunk)?;
+
+            remaining = rest;
+            offset += chunk_len as u64;
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// A `DiscIO` backend that concatenates a disc image split across several
+/// segment files (e.g. `game.part0.iso`, `game.part1.iso`, or `game.0`,
+/// `game.1`, ...) into one logical stream.
+pub struct SplitFileIO {
+    segments: Vec<Segment>,
+    len: u64
+}
+
+struct Segment {
+    file: fs::File,
+    start: u64,
+    len: u64
+}
+
+impl SplitFileIO {
+    fn open(paths: &[PathBuf]) -> Result<SplitFileIO, ImageError> {
+        let mut segments = Vec::with_capacity(paths.len());
+        let mut start = 0u64;
+        for path in paths {
+            let len = fs::metadata(path)?.len();
+            segments.push(Segment {
+                file: fs::File::open(path)?,
+                start,
+                len
+            });
+            start += len;
+        }
+        Ok(SplitFileIO {
+            segments,
+            len: start
+        })
+    }
+}
+
+impl DiscIO for SplitFileIO {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut remaining = buf;
+        let mut offset = offset;
+
+        while !remaining.is_empty() {
+            let segment = self.segments.iter_mut()
+                .find(|s| offset >= s.start && offset < s.start + s.len)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "read past end of split image"))?;
+
+            let segment_ofst = offset - segment.start;
+            let chunk_len = remaining.len().min((segment.len - segment_ofst) as usize);
+
+            let (chunk, rest) = remaining.split_at_mut(chunk_len);
+            segment.file.seek(io::SeekFrom::Start(segment_ofst))?;
+            segment.file.read_exact(chunk)?;
+
+            remaining = rest;
+            offset += chunk_len as u64;
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// Recognizes `path` as one segment of a split image (`name.partN.ext` or
+/// `name.N`) and returns every sibling segment, in order, if at least one
+/// more segment exists alongside it.
+fn detect_split_segments(path: &Path) -> Option<Vec<PathBuf>> {
+    let file_name = path.file_name()?.to_str()?;
+    let (prefix, suffix, start_index) = split_segment_pattern(file_name)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    //`path` may not be the first segment - e.g. opening `game.part1.iso` directly.
+    //Walk backward from it first, stopping at the first missing index, so the returned list
+    //starts at the lowest contiguous segment (index 0 when every earlier segment exists).
+    let mut first_index = start_index;
+    while first_index > 0 {
+        let candidate = dir.join(format!("{prefix}{}{suffix}", first_index - 1));
+        if !candidate.is_file() {
+            break;
+        }
+        first_index -= 1;
+    }
+
+    let mut segments = Vec::new();
+    let mut index = first_index;
+    loop {
+        let candidate = dir.join(format!("{prefix}{index}{suffix}"));
+        if !candidate.is_file() {
+            break;
+        }
+        segments.push(candidate);
+        index += 1;
+    }
+
+    if segments.len() > 1 {
+        Some(segments)
+    } else {
+        None
+    }
+}
+
+/// Splits a split-segment filename into `(prefix, suffix, index)` such that
+/// the filename is reconstructed as `format!("{prefix}{index}{suffix}")`.
+/// Recognizes `name.partN.ext` and the bare `name.N` convention.
+fn split_segment_pattern(file_name: &str) -> Option<(String, String, u32)> {
+    if let Some(part_pos) = file_name.find(".part") {
+        let rest = &file_name[part_pos + 5..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            let index = rest[..digits].parse().ok()?;
+            let prefix = file_name[..part_pos + 5].to_string();
+            let suffix = rest[digits..].to_string();
+            return Some((prefix, suffix, index));
+        }
+    }
+
+    let dot_pos = file_name.rfind('.')?;
+    let rest = &file_name[dot_pos + 1..];
+    if !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()) {
+        let index = rest.parse().ok()?;
+        let prefix = file_name[..dot_pos + 1].to_string();
+        return Some((prefix, String::new(), index));
+    }
+    None
+}
+
+pub(crate) fn open_backend(path: &Path) -> Result<Box<dyn DiscIO>, ImageError> {
+    if let Some(segments) = detect_split_segments(path) {
+        return Ok(Box::new(SplitFileIO::open(&segments)?));
+    }
+
+    let mut magic = [0u8; 4];
+    {
+        let mut probe = fs::File::open(path)?;
+        probe.read_exact(&mut magic)?;
+    }
+    if magic == CISO_MAGIC {
+        Ok(Box::new(CisoIO::open(path)?))
+    } else {
+        Ok(Box::new(RawFileIO::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gc_image_disc_io_test_{}_{name}", std::process::id()))
+    }
+
+    #[test]
+    fn ciso_resolves_present_and_absent_blocks() {
+        let block_size: u32 = 0x800;
+        let mut data = vec![0u8; CISO_HEADER_SIZE];
+        data[0..4].copy_from_slice(&CISO_MAGIC);
+        data[4..8].copy_from_slice(&block_size.to_le_bytes());
+        //Block 0 and block 2 are present; block 1 is all zeros and isn't stored.
+        data[8] = 1;
+        data[9] = 0;
+        data[10] = 1;
+        data.extend(vec![0xAAu8; block_size as usize]);
+        data.extend(vec![0xBBu8; block_size as usize]);
+
+        let path = temp_path("blocks.ciso");
+        fs::File::create(&path).unwrap().write_all(&data).unwrap();
+
+        let mut io = CisoIO::open(&path).unwrap();
+        //Logical length only spans through the last present block (2), not the full 32760-entry map.
+        assert_eq!(io.len(), 3 * block_size as u64);
+
+        let mut block0 = vec![0u8; block_size as usize];
+        io.read_at(0, &mut block0).unwrap();
+        assert!(block0.iter().all(|&b| b == 0xAA));
+
+        let mut block1 = vec![0u8; block_size as usize];
+        io.read_at(block_size as u64, &mut block1).unwrap();
+        assert!(block1.iter().all(|&b| b == 0));
+
+        let mut block2 = vec![0u8; block_size as usize];
+        io.read_at(2 * block_size as u64, &mut block2).unwrap();
+        assert!(block2.iter().all(|&b| b == 0xBB));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn split_segment_pattern_recognizes_part_convention() {
+        assert_eq!(
+            split_segment_pattern("game.part0.iso"),
+            Some(("game.part".to_string(), ".iso".to_string(), 0))
+        );
+        assert_eq!(
+            split_segment_pattern("game.part12.iso"),
+            Some(("game.part".to_string(), ".iso".to_string(), 12))
+        );
+    }
+
+    #[test]
+    fn split_segment_pattern_recognizes_bare_numeric_convention() {
+        assert_eq!(
+            split_segment_pattern("game.0"),
+            Some(("game.".to_string(), String::new(), 0))
+        );
+        assert_eq!(
+            split_segment_pattern("game.1"),
+            Some(("game.".to_string(), String::new(), 1))
+        );
+    }
+
+    #[test]
+    fn split_segment_pattern_rejects_non_split_names() {
+        assert_eq!(split_segment_pattern("game.iso"), None);
+        assert_eq!(split_segment_pattern("game.partabc.iso"), None);
+    }
+
+    #[test]
+    fn detect_split_segments_finds_contiguous_siblings() {
+        let part0 = temp_path("split.part0.iso");
+        let part1 = temp_path("split.part1.iso");
+        fs::write(&part0, b"abc").unwrap();
+        fs::write(&part1, b"def").unwrap();
+
+        let segments = detect_split_segments(&part0).expect("should detect sibling segments");
+        assert_eq!(segments, vec![part0.clone(), part1.clone()]);
+
+        let _ = fs::remove_file(&part0);
+        let _ = fs::remove_file(&part1);
+    }
+
+    #[test]
+    fn detect_split_segments_finds_earlier_siblings_when_opened_from_a_later_segment() {
+        let part0 = temp_path("middle.part0.iso");
+        let part1 = temp_path("middle.part1.iso");
+        let part2 = temp_path("middle.part2.iso");
+        fs::write(&part0, b"abc").unwrap();
+        fs::write(&part1, b"def").unwrap();
+        fs::write(&part2, b"ghi").unwrap();
+
+        //Opening the middle segment directly must still recover part0 before it.
+        let segments = detect_split_segments(&part1).expect("should detect sibling segments");
+        assert_eq!(segments, vec![part0.clone(), part1.clone(), part2.clone()]);
+
+        let _ = fs::remove_file(&part0);
+        let _ = fs::remove_file(&part1);
+        let _ = fs::remove_file(&part2);
+    }
+
+    #[test]
+    fn detect_split_segments_returns_none_without_a_sibling() {
+        let path = temp_path("lonely.part0.iso");
+        fs::write(&path, b"abc").unwrap();
+
+        assert!(detect_split_segments(&path).is_none());
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,12 +1,18 @@
 use std::path::Path;
-use std::fs;
-use std::io::prelude::*;
+use std::io::{self, Read, Seek, SeekFrom};
 use encoding_rs::{SHIFT_JIS, UTF_8};
 use thiserror::Error;
 
+mod disc_io;
+mod wii;
+mod digest;
+pub use disc_io::{DiscIO, RawFileIO, CisoIO};
+pub use wii::WiiPartitionIO;
+pub use digest::{Digests, KnownDump, VerifyResult};
+
 const DVD_HEADER_SIZE: usize = 0x0440;
 const DVD_MAGIC_NUMBER: u32 = 0xC2339F3D;
-const DVD_IMAGE_SIZE: u64 = 1_459_978_240;
+const WII_MAGIC_NUMBER: u32 = 0x5D1C9EA3;
 const GAME_NAME_SIZE: usize = 0x03e0;
 const CONSOLE_ID: u8 = 0x47; //'G' in ASCII
 const FILE_ENTRY_SIZE: usize = 0x0C;
@@ -18,18 +24,20 @@ const BANNER_SZ: usize = 6_496;
 pub enum ImageError {
     #[error("error reading file")]
     IOError(#[from] std::io::Error),
-    #[error("invalid image file")]
-    InvalidFileType,
     #[error("invalid region byte: {byte}")]
     InvalidRegion {
         byte: u8
     },
     #[error("invalid image header ({0})")]
     InvalidHeader(String),
+    #[error("invalid disc image format ({0})")]
+    InvalidFormat(String),
     #[error("invalid banner data({0})")]
     InvalidBanner(String),
     #[error("{0} was not found in the image")]
     FileNotFound(String),
+    #[error("{0} is a directory, not a file")]
+    NotAFile(String),
 }
 
 #[derive(Copy, Clone)]
@@ -68,7 +76,12 @@ pub struct GCImage {
     pub header: DVDHeader,
     pub banner: Banner,
     pub region: Region,
-    file: fs::File
+    io: Box<dyn DiscIO>,
+    //For Wii images, `io` above is a view over one decrypted partition, not the whole disc.
+    //This holds onto the original, still-encrypted whole-disc reader for that case, so
+    //`digests`/`verify` hash the same bytes a Redump-style record was computed over.
+    //`None` for GameCube images, where `io` already *is* the whole disc.
+    raw_io: Option<Box<dyn DiscIO>>
 }
 
 pub struct DVDHeader {
@@ -86,9 +99,16 @@ pub struct DVDHeader {
     pub max_fst_sz: u32
 }
 
+impl DVDHeader {
+    /// The 1-based disc number of a multi-disc set (disc 1, disc 2, ...), derived from `disk_id`.
+    pub fn disc_number(&self) -> u8 {
+        self.disk_id.saturating_add(1)
+    }
+}
+
 pub struct Banner {
     pub magic_word: [u8; 4],
-    pub graphical_data: [u8; 0x1800], //RGB5A1 format
+    pub graphical_data: [u8; 0x1800], //RGB5A3 format, 4x4 tiled, 96x32
     pub game_name: String,
     pub developer: String,
     pub full_game_title: String,
@@ -96,12 +116,75 @@ pub struct Banner {
     pub description: String
 }
 
+const BANNER_WIDTH: usize = 96;
+const BANNER_HEIGHT: usize = 32;
+const BANNER_TILE_SIZE: usize = 4;
+
+impl Banner {
+    /// Decodes `graphical_data` (GX RGB5A3, 4x4 tiled) into a 96x32 RGBA8
+    /// image, returned as a flat, row-major `Vec<u8>` (4 bytes per pixel).
+    pub fn decode_image(&self) -> Vec<u8> {
+        let mut rgba = vec![0u8; BANNER_WIDTH * BANNER_HEIGHT * 4];
+        let mut ofst = 0;
+        for tile_row in 0..(BANNER_HEIGHT / BANNER_TILE_SIZE) {
+            for tile_col in 0..(BANNER_WIDTH / BANNER_TILE_SIZE) {
+                for py in 0..BANNER_TILE_SIZE {
+                    for px in 0..BANNER_TILE_SIZE {
+                        let pixel = u16::from_be_bytes([self.graphical_data[ofst], self.graphical_data[ofst + 1]]);
+                        ofst += 2;
+
+                        let x = tile_col * BANNER_TILE_SIZE + px;
+                        let y = tile_row * BANNER_TILE_SIZE + py;
+                        let idx = (y * BANNER_WIDTH + x) * 4;
+                        let (r, g, b, a) = decode_rgb5a3(pixel);
+                        rgba[idx] = r;
+                        rgba[idx + 1] = g;
+                        rgba[idx + 2] = b;
+                        rgba[idx + 3] = a;
+                    }
+                }
+            }
+        }
+        rgba
+    }
+}
+
+fn decode_rgb5a3(pixel: u16) -> (u8, u8, u8, u8) {
+    if pixel & 0x8000 != 0 {
+        let r = ((pixel >> 10) & 0x1F) as u8;
+        let g = ((pixel >> 5) & 0x1F) as u8;
+        let b = (pixel & 0x1F) as u8;
+        (scale_5_to_8(r), scale_5_to_8(g), scale_5_to_8(b), 0xFF)
+    } else {
+        let a = ((pixel >> 12) & 0x7) as u8;
+        let r = ((pixel >> 8) & 0xF) as u8;
+        let g = ((pixel >> 4) & 0xF) as u8;
+        let b = (pixel & 0xF) as u8;
+        (scale_4_to_8(r), scale_4_to_8(g), scale_4_to_8(b), scale_3_to_8(a))
+    }
+}
+
+fn scale_5_to_8(v: u8) -> u8 {
+    (v << 3) | (v >> 2)
+}
+
+fn scale_4_to_8(v: u8) -> u8 {
+    (v << 4) | v
+}
+
+fn scale_3_to_8(v: u8) -> u8 {
+    (v << 5) | (v << 2) | (v >> 1)
+}
+
 pub struct FileData {
     file_offset: u32,
     file_length: u32
 }
 
 pub struct DirData {
+    //Index of the parent directory entry; parsed but not read anywhere, since the stack-based
+    //walk in `GCImage::files` tracks parents itself. The field is private to this crate.
+    #[allow(dead_code)]
     parent_offset: u32,
     next_offset: u32
 }
@@ -118,6 +201,7 @@ pub enum EntryType {
 
 pub struct FilesystemEntry {
     pub filename: String,
+    pub path: String,
     pub entry: EntryType
 }
 
@@ -125,47 +209,157 @@ pub struct FilesystemTree {
     files: Vec<FilesystemEntry>
 }
 
+impl FilesystemTree {
+    /// Resolves a slash-delimited absolute path (e.g. `/opening.bnr` or
+    /// `/MP3/Worlds.txt`) to its entry, if one exists.
+    pub fn find(&self, path: &str) -> Option<&FilesystemEntry> {
+        self.files.iter().find(|entry| entry.path == path)
+    }
+}
+
 impl GCImage {
     pub fn open(path: &Path) -> Result<GCImage, ImageError> {
-        let metadata = fs::metadata(path)?;
-        if metadata.len() != DVD_IMAGE_SIZE {
-            return Err(ImageError::InvalidFileType);
-        }
-        let mut file = fs::File::open(path)?;
-        file.seek(std::io::SeekFrom::Start(0))?;
+        let mut whole_disc_io = disc_io::open_backend(path)?;
+        let mut magic = [0u8; 4];
+        whole_disc_io.read_at(0x1c, &mut magic)?;
+        let magic = u8_arr_to_u32(&magic);
+
+        let (mut io, raw_io): (Box<dyn DiscIO>, Option<Box<dyn DiscIO>>) = if magic == WII_MAGIC_NUMBER {
+            let partition_io = wii::open_game_partition(disc_io::open_backend(path)?)?;
+            (Box::new(partition_io), Some(whole_disc_io))
+        } else {
+            (whole_disc_io, None)
+        };
 
         //Read and parse DVD Image header
         let mut data: [u8; DVD_HEADER_SIZE] = [0; DVD_HEADER_SIZE];
-        file.read_exact(&mut data)?;
+        io.read_at(0, &mut data)?;
         let header = parse_header(&data);
-        validate_header(&header)?;
+        validate_header(&header, magic, io.len())?;
 
         let region = Region::from_byte(header.game_code[3])?;
 
-        let root_entry = read_root_entry(&mut file, header.fst_ofst)?;
-        let banner = read_banner(&mut file, header.fst_ofst, &root_entry, region)?;
+        let root_entry = read_root_entry(io.as_mut(), header.fst_ofst, header.fst_sz)?;
+        let banner = read_banner(io.as_mut(), header.fst_ofst, &root_entry, region)?;
         validate_banner(&banner)?;
         Ok(GCImage {
             header,
             banner,
             region,
-            file
+            io,
+            raw_io
         })
     }
 
     pub fn files(&mut self) -> Result<FilesystemTree, ImageError> {
-        let root_entry = read_root_entry(&mut self.file, self.header.fst_ofst)?;
+        let root_entry = read_root_entry(self.io.as_mut(), self.header.fst_ofst, self.header.fst_sz)?;
         let str_tbl_ofst = self.header.fst_ofst + root_entry.string_table_ofst;
         let mut files = Vec::new();
-        for i in 0..root_entry.num_entries {
+
+        //Stack of directories we're currently inside, innermost last, each
+        //paired with the entry index at which its subtree ends.
+        let mut open_dirs: Vec<(u32, String)> = vec![(root_entry.num_entries, String::new())];
+        for i in 1..root_entry.num_entries {
+            while open_dirs.len() > 1 && i >= open_dirs.last().unwrap().0 {
+                open_dirs.pop();
+            }
+
             let ofst = (i * FILE_ENTRY_SIZE as u32) + self.header.fst_ofst;
-            let entry = read_entry(&mut self.file, ofst, str_tbl_ofst)?;
+            let mut entry = read_entry(self.io.as_mut(), ofst, str_tbl_ofst)?;
+            let parent_path = &open_dirs.last().unwrap().1;
+            entry.path = format!("{}/{}", parent_path, entry.filename);
+
+            if let EntryType::Directory(ref dir_data) = entry.entry {
+                open_dirs.push((dir_data.next_offset, entry.path.clone()));
+            }
             files.push(entry);
         }
         Ok(FilesystemTree {
             files
         })
     }
+
+    /// Opens a windowed reader over `entry`'s data, bounded to its
+    /// `file_offset..file_offset+file_length` range within the image.
+    pub fn open_file(&mut self, entry: &FilesystemEntry) -> Result<impl Read + Seek + '_, ImageError> {
+        match entry.entry {
+            EntryType::File(ref file_data) => {
+                Ok(FileReader {
+                    io: self.io.as_mut(),
+                    offset: file_data.file_offset as u64,
+                    length: file_data.file_length as u64,
+                    pos: 0
+                })
+            },
+            EntryType::Directory(_) => Err(ImageError::NotAFile(entry.path.clone()))
+        }
+    }
+
+    /// Convenience wrapper around [`GCImage::open_file`] that reads the entry's data into a `Vec<u8>`.
+    pub fn read_file_to_vec(&mut self, entry: &FilesystemEntry) -> Result<Vec<u8>, ImageError> {
+        let mut reader = self.open_file(entry)?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Computes the CRC32, MD5, and SHA-1 of the full logical image in a single streaming pass.
+    pub fn digests(&mut self) -> Result<Digests, ImageError> {
+        digest::compute_digests(self.whole_disc_io())
+    }
+
+    /// Checks this image's digests and size against a table of known-good dumps
+    /// (e.g. a Redump datfile), reporting whether it matches one.
+    pub fn verify(&mut self, known_dumps: &[KnownDump]) -> Result<VerifyResult, ImageError> {
+        let len = self.whole_disc_io().len();
+        let digests = self.digests()?;
+        let game_code = String::from_utf8_lossy(&self.header.game_code).to_string();
+        Ok(digest::verify(&game_code, len, &digests, known_dumps))
+    }
+
+    /// The `DiscIO` backend reading the whole, still-encrypted disc —
+    /// `io` itself for GameCube/raw images, the retained `raw_io` for Wii.
+    fn whole_disc_io(&mut self) -> &mut dyn DiscIO {
+        match &mut self.raw_io {
+            Some(raw_io) => raw_io.as_mut(),
+            None => self.io.as_mut()
+        }
+    }
+}
+
+struct FileReader<'a> {
+    io: &'a mut dyn DiscIO,
+    offset: u64,
+    length: u64,
+    pos: u64
+}
+
+impl<'a> Read for FileReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.length.saturating_sub(self.pos);
+        let to_read = (buf.len() as u64).min(remaining) as usize;
+        if to_read == 0 {
+            return Ok(0);
+        }
+        self.io.read_at(self.offset + self.pos, &mut buf[..to_read])?;
+        self.pos += to_read as u64;
+        Ok(to_read)
+    }
+}
+
+impl<'a> Seek for FileReader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(ofst) => ofst as i64,
+            SeekFrom::End(ofst) => self.length as i64 + ofst,
+            SeekFrom::Current(ofst) => self.pos as i64 + ofst
+        };
+        if new_pos < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position"));
+        }
+        self.pos = new_pos as u64;
+        Ok(self.pos)
+    }
 }
 
 impl IntoIterator for FilesystemTree {
@@ -211,16 +405,15 @@ fn parse_header(data: &[u8]) -> DVDHeader {
     }
 }
 
-fn read_banner(file: &mut fs::File, fst_ofst: u32, root_entry: &RootDirectory, region: Region) -> Result<Banner, ImageError> {
-    let banner_entry = find_file(file, fst_ofst, root_entry, BANNER_NAME)?;
+fn read_banner(io: &mut dyn DiscIO, fst_ofst: u32, root_entry: &RootDirectory, region: Region) -> Result<Banner, ImageError> {
+    let banner_entry = find_file(io, fst_ofst, root_entry, BANNER_NAME)?;
     match banner_entry.entry {
         EntryType::File(file_data) => {
             let mut data = [0; BANNER_SZ];
             if file_data.file_length as usize != BANNER_SZ {
                 return Err(ImageError::InvalidBanner("malformed banner file".to_string()));
             }
-            file.seek(std::io::SeekFrom::Start(file_data.file_offset as u64))?;
-            file.read_exact(&mut data)?;
+            io.read_at(file_data.file_offset as u64, &mut data)?;
 
             let mut magic_word = [0; 0x4];
             magic_word.copy_from_slice(&data[0..0x4]);
@@ -245,10 +438,9 @@ fn read_banner(file: &mut fs::File, fst_ofst: u32, root_entry: &RootDirectory, r
     }
 }
 
-fn read_root_entry(file: &mut fs::File, fst_ofst: u32) -> Result<RootDirectory, ImageError> {
-    file.seek(std::io::SeekFrom::Start(fst_ofst as u64))?;
+fn read_root_entry(io: &mut dyn DiscIO, fst_ofst: u32, fst_sz: u32) -> Result<RootDirectory, ImageError> {
     let mut data = [0; FILE_ENTRY_SIZE];
-    file.read_exact(&mut data)?;
+    io.read_at(fst_ofst as u64, &mut data)?;
 
     let flags = data[0];
     //Root Entry Should always be a directory
@@ -256,7 +448,12 @@ fn read_root_entry(file: &mut fs::File, fst_ofst: u32) -> Result<RootDirectory,
         return Err(ImageError::InvalidHeader("invalid root directory entry".to_string()));
     }
     let num_entries = u8_arr_to_u32(&data[0x08..0x0C]);
-    let string_table_ofst = num_entries * FILE_ENTRY_SIZE as u32;
+    //A corrupted/homebrew image can claim an entry count that doesn't fit inside its own
+    //filesystem table; reject it here instead of overflowing this multiplication (and every
+    //per-entry offset computed from it) later on.
+    let string_table_ofst = num_entries.checked_mul(FILE_ENTRY_SIZE as u32)
+        .filter(|&ofst| ofst <= fst_sz)
+        .ok_or_else(|| ImageError::InvalidHeader("root directory entry count overflows the filesystem table".to_string()))?;
 
     Ok(RootDirectory {
         num_entries,
@@ -264,15 +461,14 @@ fn read_root_entry(file: &mut fs::File, fst_ofst: u32) -> Result<RootDirectory,
     })
 }
 
-fn read_entry(file: &mut fs::File, ofst: u32, string_table_ofst: u32) -> Result<FilesystemEntry, ImageError> {
-    file.seek(std::io::SeekFrom::Start(ofst as u64))?;
+fn read_entry(io: &mut dyn DiscIO, ofst: u32, string_table_ofst: u32) -> Result<FilesystemEntry, ImageError> {
     let mut data = [0; FILE_ENTRY_SIZE];
-    file.read_exact(&mut data)?;
+    io.read_at(ofst as u64, &mut data)?;
 
     let flags = data[0];
     let filename_ofst = u8_arr_to_u24(&data[0x01..0x04]);
     let ofst = filename_ofst + string_table_ofst;
-    let filename = read_string(file, ofst as u64);
+    let filename = read_string(io, ofst as u64);
     let entry = if flags == 0 {
         //File
         let file_offset = u8_arr_to_u32(&data[0x04..0x08]);
@@ -291,16 +487,20 @@ fn read_entry(file: &mut fs::File, ofst: u32, string_table_ofst: u32) -> Result<
         })
     };
 
+    //Callers that walk the tree (`GCImage::files`) fill in the real path;
+    //this is just a reasonable default for entries read standalone (e.g. `find_file`).
+    let path = format!("/{}", filename);
     Ok(FilesystemEntry {
         entry,
-        filename
+        filename,
+        path
     })
 }
 
-fn find_file(img_file: &mut fs::File, fst_ofst: u32, root_entry: &RootDirectory, name: &str) -> Result<FilesystemEntry, ImageError> {
+fn find_file(io: &mut dyn DiscIO, fst_ofst: u32, root_entry: &RootDirectory, name: &str) -> Result<FilesystemEntry, ImageError> {
     for i in 0..root_entry.num_entries {
         let ofst = ( i * FILE_ENTRY_SIZE as u32 ) + fst_ofst;
-        let entry = read_entry(img_file, ofst, root_entry.string_table_ofst + fst_ofst)?;
+        let entry = read_entry(io, ofst, root_entry.string_table_ofst + fst_ofst)?;
         match entry.entry {
             EntryType::File(_) => {
                 if entry.filename == name {
@@ -313,17 +513,18 @@ fn find_file(img_file: &mut fs::File, fst_ofst: u32, root_entry: &RootDirectory,
     Err(ImageError::FileNotFound(name.to_string()))
 }
 
-fn read_string(file: &mut fs::File, ofst: u64) -> String {
+fn read_string(io: &mut dyn DiscIO, ofst: u64) -> String {
     let mut bytes = Vec::new();
+    let mut byte = [0u8; 1];
 
-    file.seek(std::io::SeekFrom::Start(ofst as u64)).unwrap();
-
-    for byte in file.bytes() {
-        let byte = byte.unwrap();
-        if byte == 0 {
+    let mut ofst = ofst;
+    loop {
+        io.read_at(ofst, &mut byte).unwrap();
+        if byte[0] == 0 {
             break;
         }
-        bytes.push(byte);
+        bytes.push(byte[0]);
+        ofst += 1;
     }
 
     String::from_utf8(bytes).unwrap()
@@ -344,17 +545,21 @@ fn byte_slice_to_string(bytes: &[u8], region: Region) -> String {
     }
 }
 
-fn validate_header(hdr: &DVDHeader) -> Result<(), ImageError> {
-    if hdr.magic_word != DVD_MAGIC_NUMBER {
+fn validate_header(hdr: &DVDHeader, expected_magic: u32, image_len: u64) -> Result<(), ImageError> {
+    if hdr.magic_word != expected_magic {
         return Err(ImageError::InvalidHeader("incorrect or missing magic number".to_string()));
     }
-    if (hdr.fst_ofst as u64) >= DVD_IMAGE_SIZE {
+    if (hdr.fst_ofst as u64) >= image_len {
         return Err(ImageError::InvalidHeader("malformed filesystem table offset".to_string()));
     }
-    if (hdr.dol_ofst as u64) >= DVD_IMAGE_SIZE {
+    if (hdr.dol_ofst as u64) >= image_len {
         return Err(ImageError::InvalidHeader("malformed bootfile offset".to_string()));
     }
-    if hdr.game_code[0] != CONSOLE_ID {
+    if (hdr.fst_ofst as u64).saturating_add(hdr.fst_sz as u64) > image_len {
+        return Err(ImageError::InvalidHeader("filesystem table extends past the end of the image".to_string()));
+    }
+    //The GameCube console id isn't meaningful for Wii titles, which use their own game codes.
+    if expected_magic == DVD_MAGIC_NUMBER && hdr.game_code[0] != CONSOLE_ID {
         return Err(ImageError::InvalidHeader("incorrect console id".to_string()));
     }
     Ok(())
@@ -390,8 +595,202 @@ fn u8_arr_to_u24(arr: &[u8]) -> u32 {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    /// A `DiscIO` backed by an in-memory buffer, for exercising header/FST
+    /// parsing without a real disc image on disk.
+    struct MemDiscIO {
+        data: Vec<u8>
+    }
+
+    impl DiscIO for MemDiscIO {
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+            let start = offset as usize;
+            let end = start + buf.len();
+            if end > self.data.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "read past end of test image"));
+            }
+            buf.copy_from_slice(&self.data[start..end]);
+            Ok(())
+        }
+
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+    }
+
+    #[test]
+    fn read_root_entry_rejects_entry_count_overflowing_fst_size() {
+        let mut data = vec![0u8; FILE_ENTRY_SIZE];
+        data[0] = 1; //Root entry must be a directory.
+        //An entry count this large overflows `num_entries * FILE_ENTRY_SIZE` and
+        //wildly exceeds any plausible filesystem table size.
+        data[0x08..0x0C].copy_from_slice(&0xFFFF_FFFFu32.to_be_bytes());
+        let mut io = MemDiscIO { data };
+
+        match read_root_entry(&mut io, 0, 0x1000) {
+            Err(ImageError::InvalidHeader(_)) => {},
+            Err(other) => panic!("expected InvalidHeader, got {other:?}"),
+            Ok(_) => panic!("expected an error, got Ok")
+        }
+    }
+
     #[test]
     fn load_iso() {
         assert!(true);
     }
+
+    #[test]
+    fn scale_functions_fill_the_full_8_bit_range() {
+        assert_eq!(scale_5_to_8(0x00), 0x00);
+        assert_eq!(scale_5_to_8(0x1F), 0xFF);
+        assert_eq!(scale_4_to_8(0x0), 0x00);
+        assert_eq!(scale_4_to_8(0xF), 0xFF);
+        assert_eq!(scale_3_to_8(0x0), 0x00);
+        assert_eq!(scale_3_to_8(0x7), 0xFF);
+    }
+
+    #[test]
+    fn decode_rgb5a3_decodes_opaque_rgb555_pixel() {
+        //Bit 15 set selects the opaque RGB555 encoding; alpha is always 0xFF.
+        let pixel: u16 = 0x8000 | (0x1F << 10) | 0x1F;
+        assert_eq!(decode_rgb5a3(pixel), (0xFF, 0x00, 0xFF, 0xFF));
+    }
+
+    #[test]
+    fn decode_rgb5a3_decodes_translucent_argb3444_pixel() {
+        //Bit 15 clear selects the 3-bit alpha + 4-bit RGB encoding.
+        let pixel: u16 = (0x7 << 12) | (0xF << 8) | 0xF;
+        assert_eq!(decode_rgb5a3(pixel), (0xFF, 0x00, 0xFF, 0xFF));
+
+        let transparent: u16 = 0x0000;
+        assert_eq!(decode_rgb5a3(transparent), (0x00, 0x00, 0x00, 0x00));
+    }
+
+    #[test]
+    fn filesystem_tree_find_resolves_by_full_path() {
+        let tree = FilesystemTree {
+            files: vec![
+                FilesystemEntry {
+                    filename: "MP3".to_string(),
+                    path: "/MP3".to_string(),
+                    entry: EntryType::Directory(DirData {
+                        parent_offset: 0,
+                        next_offset: 3
+                    })
+                },
+                FilesystemEntry {
+                    filename: "Worlds.txt".to_string(),
+                    path: "/MP3/Worlds.txt".to_string(),
+                    entry: EntryType::File(FileData {
+                        file_offset: 0x1000,
+                        file_length: 0x20
+                    })
+                },
+                FilesystemEntry {
+                    filename: "opening.bnr".to_string(),
+                    path: "/opening.bnr".to_string(),
+                    entry: EntryType::File(FileData {
+                        file_offset: 0x2000,
+                        file_length: BANNER_SZ as u32
+                    })
+                }
+            ]
+        };
+
+        let found = tree.find("/MP3/Worlds.txt").expect("entry should be found");
+        assert_eq!(found.filename, "Worlds.txt");
+        match found.entry {
+            EntryType::File(ref file_data) => assert_eq!(file_data.file_offset, 0x1000),
+            EntryType::Directory(_) => panic!("expected a file entry")
+        }
+
+        assert!(tree.find("/MP3/Missing.txt").is_none());
+        assert!(tree.find("/Worlds.txt").is_none());
+    }
+
+    fn be24(v: u32) -> [u8; 3] {
+        [(v >> 16) as u8, (v >> 8) as u8, v as u8]
+    }
+
+    fn fst_entry(flags: u8, name_ofst: u32, field1: u32, field2: u32) -> [u8; FILE_ENTRY_SIZE] {
+        let mut entry = [0u8; FILE_ENTRY_SIZE];
+        entry[0] = flags;
+        entry[1..4].copy_from_slice(&be24(name_ofst));
+        entry[4..8].copy_from_slice(&field1.to_be_bytes());
+        entry[8..12].copy_from_slice(&field2.to_be_bytes());
+        entry
+    }
+
+    fn empty_banner() -> Banner {
+        Banner {
+            magic_word: *b"BNR1",
+            graphical_data: [0u8; 0x1800],
+            game_name: String::new(),
+            developer: String::new(),
+            full_game_title: String::new(),
+            full_developer_name: String::new(),
+            description: String::new()
+        }
+    }
+
+    #[test]
+    fn files_walks_nested_directories_via_the_open_dirs_stack() {
+        //A root, a "MP3" directory containing one file, and a file back at the root -
+        //exercises both descending into a subdirectory and popping back out of it.
+        let mut data = Vec::new();
+        data.extend_from_slice(&fst_entry(1, 0, 0, 4)); //root: num_entries = 4
+        data.extend_from_slice(&fst_entry(1, 0, 0, 3)); //"MP3": next_offset = 3 (subtree is just entry 2)
+        data.extend_from_slice(&fst_entry(0, 4, 0x1000, 0x20)); //"Worlds.txt"
+        data.extend_from_slice(&fst_entry(0, 15, 0x2000, 0x30)); //"opening.bnr", back at root level
+        data.extend_from_slice(b"MP3\0");
+        data.extend_from_slice(b"Worlds.txt\0");
+        data.extend_from_slice(b"opening.bnr\0");
+
+        let fst_sz = data.len() as u32;
+        let io: Box<dyn DiscIO> = Box::new(MemDiscIO { data });
+
+        let mut image = GCImage {
+            header: DVDHeader {
+                game_code: *b"GALE",
+                maker_code: *b"01",
+                disk_id: 0,
+                version: 0,
+                audio_streaming: false,
+                stream_buf_sz: 0,
+                magic_word: DVD_MAGIC_NUMBER,
+                game_name: String::new(),
+                dol_ofst: 0,
+                fst_ofst: 0,
+                fst_sz,
+                max_fst_sz: fst_sz
+            },
+            banner: empty_banner(),
+            region: Region::USA,
+            io,
+            raw_io: None
+        };
+
+        let tree = image.files().unwrap();
+
+        let mp3_dir = tree.find("/MP3").expect("/MP3 should exist");
+        assert!(matches!(mp3_dir.entry, EntryType::Directory(_)));
+
+        let worlds_txt = tree.find("/MP3/Worlds.txt").expect("/MP3/Worlds.txt should exist");
+        match worlds_txt.entry {
+            EntryType::File(ref file_data) => {
+                assert_eq!(file_data.file_offset, 0x1000);
+                assert_eq!(file_data.file_length, 0x20);
+            },
+            EntryType::Directory(_) => panic!("expected a file entry")
+        }
+
+        //Popping back out of "MP3" must restore the root as the parent, not leave
+        //this entry nested under the previous directory.
+        let opening_bnr = tree.find("/opening.bnr").expect("/opening.bnr should exist back at the root");
+        match opening_bnr.entry {
+            EntryType::File(ref file_data) => assert_eq!(file_data.file_offset, 0x2000),
+            EntryType::Directory(_) => panic!("expected a file entry")
+        }
+    }
 }
@@ -0,0 +1,237 @@
+use std::io;
+use aes::Aes128;
+use aes::cipher::{BlockDecryptMut, KeyIvInit, block_padding::NoPadding};
+
+use crate::{DiscIO, ImageError};
+
+type Aes128CbcDec = cbc::Decryptor<Aes128>;
+#[cfg(test)]
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+
+const PARTITION_TABLE_OFFSET: u64 = 0x40000;
+const NUM_PARTITION_GROUPS: u64 = 4;
+const GAME_PARTITION_TYPE: u32 = 0;
+
+const CLUSTER_SIZE: usize = 0x8000;
+const CLUSTER_HASH_SIZE: usize = 0x400;
+const CLUSTER_DATA_SIZE: usize = CLUSTER_SIZE - CLUSTER_HASH_SIZE;
+const H2_HASH_OFFSET: usize = 0x3D0;
+
+/// Common key used to decrypt retail Wii title keys. Public since the
+/// format was broken years ago; without it no retail Wii partition can be
+/// read at all.
+const WII_COMMON_KEY: [u8; 16] = [
+    0xeb, 0xe4, 0x2a, 0x22, 0x5e, 0x85, 0x93, 0xe4,
+    0x48, 0xd9, 0xc5, 0x45, 0x73, 0x81, 0xaa, 0xf7
+];
+
+/// Finds the first game (type 0) partition described by the partition
+/// table at 0x40000 and returns a `DiscIO` that transparently decrypts it.
+pub(crate) fn open_game_partition(mut disc: Box<dyn DiscIO>) -> Result<WiiPartitionIO, ImageError> {
+    let mut groups = [0u8; (NUM_PARTITION_GROUPS as usize) * 8];
+    disc.read_at(PARTITION_TABLE_OFFSET, &mut groups)?;
+
+    for g in 0..NUM_PARTITION_GROUPS as usize {
+        let count = u32::from_be_bytes(groups[g * 8..g * 8 + 4].try_into().unwrap());
+        let table_ofst = (u32::from_be_bytes(groups[g * 8 + 4..g * 8 + 8].try_into().unwrap()) as u64) << 2;
+
+        for i in 0..count as u64 {
+            let mut entry = [0u8; 8];
+            disc.read_at(table_ofst + i * 8, &mut entry)?;
+            let offset = (u32::from_be_bytes(entry[0..4].try_into().unwrap()) as u64) << 2;
+            let kind = u32::from_be_bytes(entry[4..8].try_into().unwrap());
+
+            if kind == GAME_PARTITION_TYPE {
+                return WiiPartitionIO::open(disc, offset);
+            }
+        }
+    }
+    Err(ImageError::InvalidFormat("no game partition found in partition table".to_string()))
+}
+
+/// A `DiscIO` backend that transparently decrypts a single Wii partition,
+/// so the rest of the crate can read its header/FST/banner exactly like a
+/// GameCube image.
+pub struct WiiPartitionIO {
+    disc: Box<dyn DiscIO>,
+    data_offset: u64,
+    title_key: [u8; 16],
+    len: u64
+}
+
+impl WiiPartitionIO {
+    fn open(mut disc: Box<dyn DiscIO>, partition_offset: u64) -> Result<WiiPartitionIO, ImageError> {
+        let mut ticket = [0u8; 0x2a4];
+        disc.read_at(partition_offset, &mut ticket)?;
+
+        let mut title_key = [0u8; 16];
+        title_key.copy_from_slice(&ticket[0x1bf..0x1cf]);
+        let mut iv = [0u8; 16];
+        iv[0..8].copy_from_slice(&ticket[0x1dc..0x1e4]);
+
+        Aes128CbcDec::new(&WII_COMMON_KEY.into(), &iv.into())
+            .decrypt_padded_mut::<NoPadding>(&mut title_key)
+            .map_err(|_| ImageError::InvalidFormat("failed to decrypt title key".to_string()))?;
+
+        let mut partition_header = [0u8; 0x1c];
+        disc.read_at(partition_offset + 0x2a4, &mut partition_header)?;
+        let data_offset = (u32::from_be_bytes(partition_header[0x14..0x18].try_into().unwrap()) as u64) << 2;
+        let data_size = (u32::from_be_bytes(partition_header[0x18..0x1c].try_into().unwrap()) as u64) << 2;
+
+        Ok(WiiPartitionIO {
+            disc,
+            data_offset: partition_offset + data_offset,
+            title_key,
+            len: (data_size / CLUSTER_SIZE as u64) * CLUSTER_DATA_SIZE as u64
+        })
+    }
+
+    fn decrypt_cluster(&mut self, cluster: u64) -> Result<[u8; CLUSTER_DATA_SIZE], ImageError> {
+        let mut raw = [0u8; CLUSTER_SIZE];
+        self.disc.read_at(self.data_offset + cluster * CLUSTER_SIZE as u64, &mut raw)?;
+
+        let (hash_block, data_block) = raw.split_at_mut(CLUSTER_HASH_SIZE);
+        Aes128CbcDec::new(&self.title_key.into(), &[0u8; 16].into())
+            .decrypt_padded_mut::<NoPadding>(hash_block)
+            .map_err(|_| ImageError::InvalidFormat("failed to decrypt cluster hash block".to_string()))?;
+
+        let mut data_iv = [0u8; 16];
+        data_iv.copy_from_slice(&hash_block[H2_HASH_OFFSET..H2_HASH_OFFSET + 16]);
+
+        let mut data = [0u8; CLUSTER_DATA_SIZE];
+        data.copy_from_slice(data_block);
+        Aes128CbcDec::new(&self.title_key.into(), &data_iv.into())
+            .decrypt_padded_mut::<NoPadding>(&mut data)
+            .map_err(|_| ImageError::InvalidFormat("failed to decrypt cluster data".to_string()))?;
+
+        Ok(data)
+    }
+}
+
+impl DiscIO for WiiPartitionIO {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut remaining = buf;
+        let mut offset = offset;
+
+        while !remaining.is_empty() {
+            let cluster = offset / CLUSTER_DATA_SIZE as u64;
+            let cluster_ofst = (offset % CLUSTER_DATA_SIZE as u64) as usize;
+            let chunk_len = remaining.len().min(CLUSTER_DATA_SIZE - cluster_ofst);
+
+            let decrypted = self.decrypt_cluster(cluster)
+                .map_err(io::Error::other)?;
+
+            let (chunk, rest) = remaining.split_at_mut(chunk_len);
+            chunk.copy_from_slice(&decrypted[cluster_ofst..cluster_ofst + chunk_len]);
+
+            remaining = rest;
+            offset += chunk_len as u64;
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+
+    /// A `DiscIO` backed by an in-memory buffer, for feeding a synthetic
+    /// encrypted cluster straight into `WiiPartitionIO` without a real disc.
+    struct MemDiscIO {
+        data: Vec<u8>
+    }
+
+    impl DiscIO for MemDiscIO {
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+            let start = offset as usize;
+            let end = start + buf.len();
+            if end > self.data.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "read past end of test image"));
+            }
+            buf.copy_from_slice(&self.data[start..end]);
+            Ok(())
+        }
+
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+    }
+
+    fn aes_cbc_encrypt(key: &[u8; 16], iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+        let mut buf = plaintext.to_vec();
+        let len = buf.len();
+        Aes128CbcEnc::new(key.into(), iv.into())
+            .encrypt_padded_mut::<NoPadding>(&mut buf, len)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn decrypt_cluster_recovers_plaintext_via_h2_hash_iv() {
+        let title_key = [0x42u8; 16];
+
+        //The decrypted hash block's H2 hash area supplies the data block's IV;
+        //use a distinct, recognizable one so a transposed offset would show up.
+        let data_iv = [0x11u8; 16];
+        let mut hash_block_pt = vec![0u8; CLUSTER_HASH_SIZE];
+        hash_block_pt[H2_HASH_OFFSET..H2_HASH_OFFSET + 16].copy_from_slice(&data_iv);
+        let hash_block_ct = aes_cbc_encrypt(&title_key, &[0u8; 16], &hash_block_pt);
+
+        let data_block_pt = vec![0xCDu8; CLUSTER_DATA_SIZE];
+        let data_block_ct = aes_cbc_encrypt(&title_key, &data_iv, &data_block_pt);
+
+        let mut raw_cluster = hash_block_ct;
+        raw_cluster.extend_from_slice(&data_block_ct);
+        assert_eq!(raw_cluster.len(), CLUSTER_SIZE);
+
+        let mut io = WiiPartitionIO {
+            disc: Box::new(MemDiscIO { data: raw_cluster }),
+            data_offset: 0,
+            title_key,
+            len: CLUSTER_DATA_SIZE as u64
+        };
+
+        let mut out = vec![0u8; CLUSTER_DATA_SIZE];
+        io.read_at(0, &mut out).unwrap();
+        assert_eq!(out, data_block_pt);
+    }
+
+    #[test]
+    fn read_at_spans_multiple_clusters() {
+        let title_key = [0x07u8; 16];
+        let data_iv = [0x99u8; 16];
+
+        let mut hash_block_pt = vec![0u8; CLUSTER_HASH_SIZE];
+        hash_block_pt[H2_HASH_OFFSET..H2_HASH_OFFSET + 16].copy_from_slice(&data_iv);
+        let hash_block_ct = aes_cbc_encrypt(&title_key, &[0u8; 16], &hash_block_pt);
+
+        //Two clusters, each filled with a distinct byte so a miscomputed cluster index is visible.
+        let cluster0_pt = vec![0xAAu8; CLUSTER_DATA_SIZE];
+        let cluster1_pt = vec![0xBBu8; CLUSTER_DATA_SIZE];
+        let cluster0_ct = aes_cbc_encrypt(&title_key, &data_iv, &cluster0_pt);
+        let cluster1_ct = aes_cbc_encrypt(&title_key, &data_iv, &cluster1_pt);
+
+        let mut raw = Vec::with_capacity(CLUSTER_SIZE * 2);
+        raw.extend_from_slice(&hash_block_ct);
+        raw.extend_from_slice(&cluster0_ct);
+        raw.extend_from_slice(&hash_block_ct);
+        raw.extend_from_slice(&cluster1_ct);
+
+        let mut io = WiiPartitionIO {
+            disc: Box::new(MemDiscIO { data: raw }),
+            data_offset: 0,
+            title_key,
+            len: CLUSTER_DATA_SIZE as u64 * 2
+        };
+
+        let mut out = vec![0u8; CLUSTER_DATA_SIZE * 2];
+        io.read_at(0, &mut out).unwrap();
+        assert_eq!(&out[..CLUSTER_DATA_SIZE], cluster0_pt.as_slice());
+        assert_eq!(&out[CLUSTER_DATA_SIZE..], cluster1_pt.as_slice());
+    }
+}